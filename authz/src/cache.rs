@@ -0,0 +1,371 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash, Hasher},
+    num::NonZeroUsize,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use iox_time::{SystemProvider, Time, TimeProvider};
+use lru::LruCache;
+use metric::{Metric, Registry, U64Counter};
+use parking_lot::Mutex;
+
+use super::{Authorizer, Error, Permission};
+
+const AUTHZ_CACHE_METRIC: &str = "authz_permissions_cache";
+
+/// A cached, successful [`Authorizer::permissions()`] response and the time
+/// at which it should be considered stale.
+///
+/// `token` and `perms` are retained alongside the cached result so a cache
+/// lookup can verify them against the request that produced a `u64` hash
+/// match before serving it - the hash is only used to pick a cache slot,
+/// never treated as proof of identity on its own.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    token: Option<Vec<u8>>,
+    perms: Vec<Permission>,
+    permissions: Vec<Permission>,
+    expires_at: Time,
+}
+
+/// A caching decorator over an [`Authorizer`] implementation.
+///
+/// Successful [`Authorizer::permissions()`] responses are cached for `ttl`,
+/// keyed on a hash of the caller's token and requested permission set, to
+/// avoid paying RPC latency for repeated checks (the
+/// [`AuthorizerInstrumentation`](super::AuthorizerInstrumentation) wrapper
+/// shows these calls dominate request latency). The cache is bounded to
+/// `capacity` entries with LRU eviction.
+///
+/// Error responses are never cached (a recovering backend should be
+/// re-checked immediately, rather than locking callers out for `ttl`), so
+/// this decorator composes cleanly on either side of
+/// [`AuthorizerInstrumentation`] - wrapping it so its latency metrics
+/// continue to reflect only real RPC calls, or being wrapped by it to also
+/// observe cache-hit latency.
+#[derive(Debug)]
+pub struct AuthorizerCache<T, P = SystemProvider> {
+    inner: T,
+    time_provider: P,
+
+    /// Keyed on a hash of `(token, perms)` derived via `hasher_builder`;
+    /// see [`Self::cache_key()`] and [`CacheEntry`] for why the hash alone
+    /// is not trusted as proof of a match.
+    cache: Mutex<LruCache<u64, CacheEntry>>,
+    hasher_builder: RandomState,
+    ttl: Duration,
+
+    /// Cache hits, misses, and TTL expirations, faceted by `result`.
+    cache_hit: U64Counter,
+    cache_miss: U64Counter,
+    cache_expired: U64Counter,
+}
+
+impl<T> AuthorizerCache<T> {
+    /// Wrap `inner`, caching successful permission checks for `ttl`, with at
+    /// most `capacity` entries held at once.
+    pub fn new(registry: &Registry, inner: T, ttl: Duration, capacity: NonZeroUsize) -> Self {
+        let metric: Metric<U64Counter> = registry.register_metric(
+            AUTHZ_CACHE_METRIC,
+            "the number of authz permissions cache hits, misses and expirations",
+        );
+
+        let cache_hit = metric.recorder(&[("result", "hit")]);
+        let cache_miss = metric.recorder(&[("result", "miss")]);
+        let cache_expired = metric.recorder(&[("result", "expired")]);
+
+        Self {
+            inner,
+            time_provider: Default::default(),
+            cache: Mutex::new(LruCache::new(capacity)),
+            hasher_builder: RandomState::new(),
+            ttl,
+            cache_hit,
+            cache_miss,
+            cache_expired,
+        }
+    }
+}
+
+impl<T, P> AuthorizerCache<T, P> {
+    /// Hash `token` and `perms` together into a cache slot key.
+    ///
+    /// This selects a slot, but is not by itself sufficient to confirm a
+    /// cache hit: it is a 64-bit digest built with this cache's own
+    /// [`RandomState`] rather than a collision-resistant hash, so an
+    /// accidental or engineered collision between two distinct
+    /// `(token, perms)` pairs is possible. Callers must verify the looked-up
+    /// [`CacheEntry`]'s `token`/`perms` match before trusting its
+    /// `permissions` - serving a hash collision as a hit would leak one
+    /// caller's permissions to another.
+    fn cache_key(&self, token: &Option<Vec<u8>>, perms: &[Permission]) -> u64 {
+        let mut hasher = self.hasher_builder.build_hasher();
+        token.hash(&mut hasher);
+        perms.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[async_trait]
+impl<T, P> Authorizer for AuthorizerCache<T, P>
+where
+    T: Authorizer,
+    P: TimeProvider,
+{
+    async fn permissions(
+        &self,
+        token: Option<Vec<u8>>,
+        perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        let key = self.cache_key(&token, perms);
+        let now = self.time_provider.now();
+
+        match self.cache.lock().get(&key).cloned() {
+            // Only trust the hash match once the original token/perms are
+            // confirmed to be identical - otherwise this would risk serving
+            // a hash collision as someone else's cached permissions.
+            Some(entry) if entry.token == token && entry.perms == perms => {
+                if entry.expires_at > now {
+                    self.cache_hit.inc(1);
+                    return Ok(entry.permissions);
+                }
+                self.cache_expired.inc(1);
+            }
+            Some(_) | None => self.cache_miss.inc(1),
+        }
+
+        let token_for_cache = token.clone();
+        let res = self.inner.permissions(token, perms).await;
+
+        if let Ok(permissions) = &res {
+            self.cache.lock().put(
+                key,
+                CacheEntry {
+                    token: token_for_cache,
+                    perms: perms.to_vec(),
+                    permissions: permissions.clone(),
+                    expires_at: now + self.ttl,
+                },
+            );
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use metric::Attributes;
+    use parking_lot::Mutex as StdMutex;
+
+    use super::*;
+    use crate::{Action, Resource};
+
+    #[derive(Debug, Default)]
+    struct MockAuthorizerState {
+        ret: VecDeque<Result<Vec<Permission>, Error>>,
+        call_count: usize,
+    }
+
+    #[derive(Debug, Default)]
+    struct MockAuthorizer {
+        state: StdMutex<MockAuthorizerState>,
+    }
+
+    impl MockAuthorizer {
+        fn with_permissions_return(self, ret: impl Into<VecDeque<Result<Vec<Permission>, Error>>>) -> Self {
+            self.state.lock().ret = ret.into();
+            self
+        }
+
+        fn call_count(&self) -> usize {
+            self.state.lock().call_count
+        }
+    }
+
+    #[async_trait]
+    impl Authorizer for MockAuthorizer {
+        async fn permissions(
+            &self,
+            _token: Option<Vec<u8>>,
+            _perms: &[Permission],
+        ) -> Result<Vec<Permission>, Error> {
+            let mut state = self.state.lock();
+            state.call_count += 1;
+            state.ret.pop_front().expect("no mock sink value to return")
+        }
+    }
+
+    fn get_counter(registry: &Registry, result: &'static str) -> u64 {
+        registry
+            .get_instrument::<Metric<U64Counter>>(AUTHZ_CACHE_METRIC)
+            .expect("metric should exist")
+            .get_observer(&Attributes::from(&[("result", result)]))
+            .expect("metric should have labels")
+            .fetch()
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_avoids_inner_call() {
+        let registry = Registry::default();
+        let perm = Permission::ResourceAction(Resource::Database("foo".to_string()), Action::Write);
+
+        let cached = AuthorizerCache::new(
+            &registry,
+            MockAuthorizer::default().with_permissions_return([Ok(vec![perm.clone()])]),
+            Duration::from_secs(60),
+            NonZeroUsize::new(16).unwrap(),
+        );
+
+        let token = Some(b"token".to_vec());
+
+        let got = cached.permissions(token.clone(), &[]).await.unwrap();
+        assert_eq!(got, vec![perm.clone()]);
+        assert_eq!(get_counter(&registry, "miss"), 1);
+
+        let got = cached.permissions(token, &[]).await.unwrap();
+        assert_eq!(got, vec![perm]);
+        assert_eq!(get_counter(&registry, "hit"), 1);
+
+        // Only the first call should have reached the inner authorizer.
+        assert_eq!(cached.inner.call_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_miss_for_distinct_keys() {
+        let registry = Registry::default();
+
+        let cached = AuthorizerCache::new(
+            &registry,
+            MockAuthorizer::default().with_permissions_return([Ok(vec![]), Ok(vec![])]),
+            Duration::from_secs(60),
+            NonZeroUsize::new(16).unwrap(),
+        );
+
+        cached
+            .permissions(Some(b"a".to_vec()), &[])
+            .await
+            .expect("first call succeeds");
+        cached
+            .permissions(Some(b"b".to_vec()), &[])
+            .await
+            .expect("second call succeeds");
+
+        assert_eq!(get_counter(&registry, "miss"), 2);
+        assert_eq!(cached.inner.call_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let registry = Registry::default();
+
+        let cached = AuthorizerCache::new(
+            &registry,
+            MockAuthorizer::default().with_permissions_return([
+                Err(Error::verification("test", "test error")),
+                Ok(vec![]),
+            ]),
+            Duration::from_secs(60),
+            NonZeroUsize::new(16).unwrap(),
+        );
+
+        let token = Some(b"token".to_vec());
+
+        assert!(cached.permissions(token.clone(), &[]).await.is_err());
+        assert!(cached.permissions(token, &[]).await.is_ok());
+
+        // Both calls should have reached the inner authorizer - the error
+        // was never cached.
+        assert_eq!(cached.inner.call_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_hash_collision_is_not_served_as_a_hit() {
+        let registry = Registry::default();
+        let real_perm = Permission::ResourceAction(Resource::Database("real".to_string()), Action::Write);
+        let colliding_perm =
+            Permission::ResourceAction(Resource::Database("colliding".to_string()), Action::Write);
+
+        let cached = AuthorizerCache::new(
+            &registry,
+            MockAuthorizer::default().with_permissions_return([Ok(vec![real_perm.clone()])]),
+            Duration::from_secs(60),
+            NonZeroUsize::new(16).unwrap(),
+        );
+
+        let token = Some(b"the-real-token".to_vec());
+
+        // Simulate a `u64` hash collision: plant an entry for a *different*
+        // token/perms under the exact key this request will hash to.
+        let key = cached.cache_key(&token, &[]);
+        cached.cache.lock().put(
+            key,
+            CacheEntry {
+                token: Some(b"a-different-token".to_vec()),
+                perms: vec![],
+                permissions: vec![colliding_perm],
+                expires_at: cached.time_provider.now() + Duration::from_secs(60),
+            },
+        );
+
+        // Despite the colliding entry occupying this request's slot, the
+        // stored token doesn't match, so it must be treated as a miss and
+        // the real authorizer consulted - never serving the colliding
+        // entry's permissions.
+        let got = cached
+            .permissions(token, &[])
+            .await
+            .expect("call succeeds via the inner authorizer");
+
+        assert_eq!(got, vec![real_perm], "must not be served the colliding entry's permissions");
+        assert_eq!(get_counter(&registry, "hit"), 0);
+        assert_eq!(get_counter(&registry, "miss"), 1);
+        assert_eq!(cached.inner.call_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entry_is_not_served_and_is_refreshed() {
+        let registry = Registry::default();
+        let stale_perm = Permission::ResourceAction(Resource::Database("stale".to_string()), Action::Write);
+        let fresh_perm = Permission::ResourceAction(Resource::Database("fresh".to_string()), Action::Write);
+
+        let cached = AuthorizerCache::new(
+            &registry,
+            MockAuthorizer::default().with_permissions_return([Ok(vec![fresh_perm.clone()])]),
+            Duration::from_secs(60),
+            NonZeroUsize::new(16).unwrap(),
+        );
+
+        let token = Some(b"token".to_vec());
+
+        // Plant an entry for this exact token/perms that already expired in
+        // the past, as if it had been cached a while ago.
+        let key = cached.cache_key(&token, &[]);
+        let now = cached.time_provider.now();
+        cached.cache.lock().put(
+            key,
+            CacheEntry {
+                token: token.clone(),
+                perms: vec![],
+                permissions: vec![stale_perm],
+                expires_at: now - Duration::from_secs(1),
+            },
+        );
+
+        // The expired entry must not be served, and the real authorizer
+        // should be consulted for a fresh value.
+        let got = cached
+            .permissions(token, &[])
+            .await
+            .expect("call succeeds via the inner authorizer");
+
+        assert_eq!(got, vec![fresh_perm], "must not be served the expired entry's permissions");
+        assert_eq!(get_counter(&registry, "hit"), 0);
+        assert_eq!(get_counter(&registry, "expired"), 1);
+        assert_eq!(cached.inner.call_count(), 1);
+    }
+}
@@ -0,0 +1,298 @@
+use std::{collections::HashMap, time::Duration};
+
+use metric::{Attributes, MetricKind, Observation, Reporter};
+use opentelemetry::{
+    metrics::{Meter, MeterProvider},
+    KeyValue,
+};
+use parking_lot::Mutex;
+
+/// Identifies one faceted time series (a metric name plus its attribute
+/// set) across successive [`OtelPushExporter::push()`] calls.
+type SeriesKey = (&'static str, Vec<(String, String)>);
+
+fn series_key(name: &'static str, attributes: &Attributes) -> SeriesKey {
+    let mut attrs: Vec<(String, String)> = attributes
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    attrs.sort();
+    (name, attrs)
+}
+
+/// Given the cumulative value most recently observed for `key`, return how
+/// much it has grown since the last call, and record `cumulative` as the
+/// new baseline.
+///
+/// `registry` instruments report cumulative totals since creation on every
+/// [`metric::Registry::report()`] call (the same contract every other
+/// instrument in this crate relies on), but OTel's synchronous counter API
+/// expects each `add()` call to be an incremental delta - recording the
+/// cumulative value on every tick would re-add every prior tick's samples.
+fn counter_delta(previous: &mut HashMap<SeriesKey, u64>, key: SeriesKey, cumulative: u64) -> u64 {
+    let delta = cumulative.saturating_sub(*previous.get(&key).unwrap_or(&0));
+    previous.insert(key, cumulative);
+    delta
+}
+
+/// As [`counter_delta`], but for a histogram's per-bucket cumulative
+/// counts: returns how much each bucket has grown since the last call, in
+/// the same order as `cumulative_counts`.
+fn histogram_bucket_deltas(
+    previous: &mut HashMap<SeriesKey, Vec<u64>>,
+    key: SeriesKey,
+    cumulative_counts: &[u64],
+) -> Vec<u64> {
+    let prev_counts = previous
+        .get(&key)
+        .cloned()
+        .unwrap_or_else(|| vec![0; cumulative_counts.len()]);
+    previous.insert(key, cumulative_counts.to_vec());
+
+    cumulative_counts
+        .iter()
+        .zip(prev_counts)
+        .map(|(count, prev)| count.saturating_sub(prev))
+        .collect()
+}
+
+/// Replay each bucket's `delta` as that many individual `record()` calls.
+///
+/// Unlike counters, whose whole [`counter_delta`] is reported in a single
+/// `add()` call, OpenTelemetry's synchronous histogram API only exposes
+/// `record(value, attributes)` for a single observation - there is no
+/// weighted/batch form that accepts a value plus a count - so reproducing
+/// `delta` bucketed observations costs `delta` `record()` calls. This means
+/// a push's cost scales with observation volume, not with the number of
+/// distinct series: a bucket that accumulates thousands of hits between
+/// ticks costs thousands of calls on that tick. Deployments with
+/// high-volume histograms should prefer
+/// [`super::prometheus::PrometheusTextEncoder`]'s pull-based scrape, which
+/// renders the cumulative bucket counts directly without replaying
+/// individual observations.
+fn replay_histogram_deltas<T: Copy>(
+    bucket_values: impl Iterator<Item = T>,
+    deltas: &[u64],
+    labels: &[KeyValue],
+    mut record: impl FnMut(T, &[KeyValue]),
+) {
+    for (value, &delta) in bucket_values.zip(deltas) {
+        for _ in 0..delta {
+            record(value, labels);
+        }
+    }
+}
+
+/// Periodically pushes a [`metric::Registry`]'s instruments to an
+/// OpenTelemetry [`Meter`], for deployments that prefer an OTLP push
+/// pipeline over being scraped.
+///
+/// Unlike [`super::prometheus::PrometheusTextEncoder`], which renders a
+/// point-in-time snapshot on demand, this exporter owns no background task
+/// itself: [`Self::push()`] is intended to be called on a `push_interval`
+/// tick from a caller-owned loop, following the same cooperative-shutdown
+/// shape used by this workspace's other background instruments. Because
+/// `registry` hands back cumulative totals on every call, the exporter
+/// tracks the previous snapshot of each counter and histogram series so
+/// only the delta since the last push is forwarded to OTel's synchronous
+/// instruments.
+#[derive(Debug)]
+pub struct OtelPushExporter {
+    meter: Meter,
+    previous_counters: Mutex<HashMap<SeriesKey, u64>>,
+    previous_histogram_buckets: Mutex<HashMap<SeriesKey, Vec<u64>>>,
+}
+
+impl OtelPushExporter {
+    /// Create a new exporter that reports instruments to a meter named
+    /// `metric_exporters` obtained from `meter_provider`.
+    pub fn new(meter_provider: &impl MeterProvider) -> Self {
+        Self {
+            meter: meter_provider.meter("metric_exporters"),
+            previous_counters: Mutex::new(HashMap::new()),
+            previous_histogram_buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sample `registry` once and forward the readings to the configured
+    /// OpenTelemetry meter.
+    pub fn push(&self, registry: &metric::Registry) {
+        let mut reporter = OtelReporter {
+            exporter: self,
+            current_name: None,
+            current_description: "",
+        };
+        registry.report(&mut reporter);
+    }
+}
+
+/// Adapts [`metric::Registry::report()`] observations into OpenTelemetry
+/// instrument recordings.
+struct OtelReporter<'a> {
+    exporter: &'a OtelPushExporter,
+    current_name: Option<&'static str>,
+    current_description: &'static str,
+}
+
+impl<'a> Reporter for OtelReporter<'a> {
+    fn start_metric(
+        &mut self,
+        metric_name: &'static str,
+        metric_description: &'static str,
+        _metric_kind: MetricKind,
+    ) {
+        self.current_name = Some(metric_name);
+        self.current_description = metric_description;
+    }
+
+    fn report_observation(&mut self, attributes: &Attributes, observation: Observation) {
+        let name = self
+            .current_name
+            .expect("report_observation() called before start_metric()");
+        let labels: Vec<KeyValue> = attributes
+            .iter()
+            .map(|(k, v)| KeyValue::new(k.to_string(), v.to_string()))
+            .collect();
+
+        match observation {
+            Observation::U64Counter(cumulative) => {
+                let delta = counter_delta(
+                    &mut self.exporter.previous_counters.lock(),
+                    series_key(name, attributes),
+                    cumulative,
+                );
+
+                if delta > 0 {
+                    self.exporter
+                        .meter
+                        .u64_counter(name)
+                        .with_description(self.current_description)
+                        .init()
+                        .add(delta, &labels);
+                }
+            }
+            Observation::U64Gauge(v) => {
+                self.exporter
+                    .meter
+                    .u64_observable_gauge(name)
+                    .with_description(self.current_description)
+                    .init()
+                    .observe(v, &labels);
+            }
+            Observation::DurationGauge(v) => {
+                self.exporter
+                    .meter
+                    .f64_observable_gauge(name)
+                    .with_description(self.current_description)
+                    .init()
+                    .observe(v.as_secs_f64(), &labels);
+            }
+            Observation::U64Histogram(hist) => {
+                let counts: Vec<u64> = hist.buckets.iter().map(|b| b.count).collect();
+                let deltas = histogram_bucket_deltas(
+                    &mut self.exporter.previous_histogram_buckets.lock(),
+                    series_key(name, attributes),
+                    &counts,
+                );
+
+                let histogram = self
+                    .exporter
+                    .meter
+                    .u64_histogram(name)
+                    .with_description(self.current_description)
+                    .init();
+                replay_histogram_deltas(
+                    hist.buckets.iter().map(|b| b.le),
+                    &deltas,
+                    &labels,
+                    |value, labels| histogram.record(value, labels),
+                );
+            }
+            Observation::DurationHistogram(hist) => {
+                let counts: Vec<u64> = hist.buckets.iter().map(|b| b.count).collect();
+                let deltas = histogram_bucket_deltas(
+                    &mut self.exporter.previous_histogram_buckets.lock(),
+                    series_key(name, attributes),
+                    &counts,
+                );
+
+                let histogram = self
+                    .exporter
+                    .meter
+                    .f64_histogram(name)
+                    .with_description(self.current_description)
+                    .init();
+                replay_histogram_deltas(
+                    hist.buckets.iter().map(|b| b.le.as_secs_f64()),
+                    &deltas,
+                    &labels,
+                    |value, labels| histogram.record(value, labels),
+                );
+            }
+        }
+    }
+
+    fn finish_metric(&mut self) {
+        self.current_name = None;
+    }
+}
+
+/// The default interval at which [`OtelPushExporter::push()`] should be
+/// driven, if the caller has no more specific requirement.
+pub const DEFAULT_PUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_delta_does_not_replay_prior_ticks() {
+        let mut previous = HashMap::new();
+        let key = series_key("requests_total", &Attributes::from([]));
+
+        // First push establishes the baseline: the whole cumulative value
+        // is new.
+        assert_eq!(counter_delta(&mut previous, key.clone(), 3), 3);
+
+        // A later push with a higher cumulative value should only report
+        // the growth since the last push, not the new total again.
+        assert_eq!(counter_delta(&mut previous, key.clone(), 7), 4);
+
+        // No growth since the last push means no delta.
+        assert_eq!(counter_delta(&mut previous, key, 7), 0);
+    }
+
+    #[test]
+    fn test_histogram_bucket_deltas_does_not_replay_prior_ticks() {
+        let mut previous = HashMap::new();
+        let key = series_key("latency_seconds", &Attributes::from([]));
+
+        assert_eq!(
+            histogram_bucket_deltas(&mut previous, key.clone(), &[1, 2, 5]),
+            vec![1, 2, 5]
+        );
+
+        // Re-pushing the same cumulative snapshot (no new observations)
+        // must not re-add the previous samples.
+        assert_eq!(
+            histogram_bucket_deltas(&mut previous, key.clone(), &[1, 2, 5]),
+            vec![0, 0, 0]
+        );
+
+        assert_eq!(
+            histogram_bucket_deltas(&mut previous, key, &[1, 3, 9]),
+            vec![0, 1, 4]
+        );
+    }
+
+    #[test]
+    fn test_replay_histogram_deltas_records_delta_count_per_bucket() {
+        let mut recorded = Vec::new();
+
+        replay_histogram_deltas([1.0, 2.0, 5.0].into_iter(), &[2, 0, 1], &[], |value, _labels| {
+            recorded.push(value);
+        });
+
+        assert_eq!(recorded, vec![1.0, 1.0, 5.0]);
+    }
+}
@@ -0,0 +1,268 @@
+use std::{convert::Infallible, fmt::Write as _, sync::Arc};
+
+use hyper::{Body, Request, Response};
+use metric::{Attributes, MetricKind, Observation, Reporter};
+
+/// Renders a [`metric::Registry`] in [Prometheus text exposition format].
+///
+/// [Prometheus text exposition format]: https://github.com/prometheus/docs/blob/main/content/docs/instrumenting/exposition_formats.md
+#[derive(Debug, Default)]
+pub struct PrometheusTextEncoder {
+    buf: String,
+    current: Option<String>,
+}
+
+impl PrometheusTextEncoder {
+    /// Render `registry`'s instruments as Prometheus exposition-format text.
+    pub fn encode(registry: &metric::Registry) -> String {
+        let mut encoder = Self::default();
+        registry.report(&mut encoder);
+        encoder.buf
+    }
+
+    fn write_sample(&mut self, name: &str, labels: &str, value: impl std::fmt::Display) {
+        let _ = writeln!(self.buf, "{name}{labels} {value}");
+    }
+
+    /// Write the `_bucket`/`_sum`/`_count` series for a histogram observation.
+    ///
+    /// `buckets` must be the histogram's non-cumulative per-bucket counts,
+    /// ascending by `le`; Prometheus buckets are cumulative, so this
+    /// accumulates them on the way out.
+    ///
+    /// `sum` is expected to be [`approximate_bucket_sum`]'s output (or
+    /// similarly derived from bucket boundaries rather than the exact
+    /// observed values) - see that function's doc comment for why `_sum`
+    /// is only an upper-bound approximation here, not an exact total.
+    fn write_histogram(
+        &mut self,
+        name: &str,
+        attributes: &Attributes,
+        buckets: &[(String, u64)],
+        sum: impl std::fmt::Display,
+        count: u64,
+    ) {
+        let mut cumulative = 0_u64;
+        for (le, observed) in buckets {
+            cumulative += observed;
+            let labels = render_labels(attributes, Some(("le", le)));
+            self.write_sample(&format!("{name}_bucket"), &labels, cumulative);
+        }
+
+        let labels = render_labels(attributes, None);
+        self.write_sample(&format!("{name}_sum"), &labels, sum);
+        self.write_sample(&format!("{name}_count"), &labels, count);
+    }
+}
+
+impl Reporter for PrometheusTextEncoder {
+    fn start_metric(
+        &mut self,
+        metric_name: &'static str,
+        metric_description: &'static str,
+        metric_kind: MetricKind,
+    ) {
+        let name = sanitize_metric_name(metric_name);
+        let type_str = match metric_kind {
+            MetricKind::U64Counter => "counter",
+            MetricKind::U64Gauge | MetricKind::DurationGauge => "gauge",
+            MetricKind::U64Histogram | MetricKind::DurationHistogram => "histogram",
+        };
+
+        let _ = writeln!(self.buf, "# HELP {name} {metric_description}");
+        let _ = writeln!(self.buf, "# TYPE {name} {type_str}");
+
+        self.current = Some(name);
+    }
+
+    fn report_observation(&mut self, attributes: &Attributes, observation: Observation) {
+        let name = self
+            .current
+            .clone()
+            .expect("report_observation() called before start_metric()");
+        let labels = render_labels(attributes, None);
+
+        match observation {
+            Observation::U64Counter(v) | Observation::U64Gauge(v) => {
+                self.write_sample(&name, &labels, v);
+            }
+            Observation::DurationGauge(v) => {
+                self.write_sample(&name, &labels, v.as_secs_f64());
+            }
+            Observation::U64Histogram(hist) => {
+                let buckets: Vec<_> = hist
+                    .buckets
+                    .iter()
+                    .map(|b| (b.le.to_string(), b.count))
+                    .collect();
+                let sum = approximate_bucket_sum(hist.buckets.iter().map(|b| (b.le as f64, b.count)));
+                let count = hist.buckets.iter().map(|b| b.count).sum();
+                self.write_histogram(&name, attributes, &buckets, sum, count);
+            }
+            Observation::DurationHistogram(hist) => {
+                let buckets: Vec<_> = hist
+                    .buckets
+                    .iter()
+                    .map(|b| (b.le.as_secs_f64().to_string(), b.count))
+                    .collect();
+                let sum =
+                    approximate_bucket_sum(hist.buckets.iter().map(|b| (b.le.as_secs_f64(), b.count)));
+                let count = hist.buckets.iter().map(|b| b.count).sum();
+                self.write_histogram(&name, attributes, &buckets, sum, count);
+            }
+        }
+    }
+
+    fn finish_metric(&mut self) {
+        self.current = None;
+    }
+}
+
+/// Approximate a histogram's `_sum` from its bucket upper bounds and
+/// per-bucket counts: `Σ le_i * count_i`.
+///
+/// This is **not** the exact sum of observed values - `metric`'s
+/// [`Observation::U64Histogram`]/[`Observation::DurationHistogram`] only
+/// carry per-bucket counts, not the exact values that fell into each
+/// bucket, so the true sum can't be reconstructed from them. Using the
+/// bucket's upper bound systematically overstates the contribution of
+/// every bucket (the true average observation in a bucket is somewhere
+/// between its lower and upper bound, never above it), and with wide,
+/// exponentially-spaced buckets like `process_memory_rss_distribution`
+/// (powers of two from ~1MiB to ~64GiB) that overstatement can approach
+/// 2x per bucket. Treat the exported `_sum` (and therefore
+/// `rate(x_sum)/rate(x_count)`) as an upper-bound estimate, not an exact
+/// average, until `metric`'s histogram observation carries an exact
+/// running sum we can report instead.
+fn approximate_bucket_sum(buckets: impl Iterator<Item = (f64, u64)>) -> f64 {
+    buckets.map(|(le, count)| le * count as f64).sum()
+}
+
+/// Prometheus metric names may only contain `[a-zA-Z0-9_:]`; replace any
+/// other character with an underscore.
+fn sanitize_metric_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Render `attributes` (plus an optional extra key/value pair, used for the
+/// histogram `le` label) as a Prometheus `{k="v",...}` label set.
+fn render_labels(attributes: &Attributes, extra: Option<(&str, &str)>) -> String {
+    let mut pairs: Vec<(String, String)> = attributes
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+    if let Some((k, v)) = extra {
+        pairs.push((k.to_string(), v.to_string()));
+    }
+
+    if pairs.is_empty() {
+        return String::new();
+    }
+
+    let body = pairs
+        .into_iter()
+        .map(|(k, v)| format!("{k}=\"{}\"", escape_label_value(&v)))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!("{{{body}}}")
+}
+
+/// Escape a label value per the [Prometheus text exposition format]:
+/// backslashes, double quotes, and newlines must all be escaped, or a
+/// literal newline in a value (e.g. a misconfigured `path`) would split one
+/// sample across two lines and corrupt the output.
+///
+/// [Prometheus text exposition format]: https://github.com/prometheus/docs/blob/main/content/docs/instrumenting/exposition_formats.md
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// A small admin HTTP handler that serves the current state of `registry` in
+/// Prometheus text exposition format, suitable for mounting at e.g.
+/// `/metrics`.
+pub async fn serve_metrics(
+    registry: Arc<metric::Registry>,
+    _req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let body = PrometheusTextEncoder::encode(&registry);
+
+    Ok(Response::builder()
+        .status(200)
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .body(Body::from(body))
+        .expect("static response is well-formed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use metric::{DurationHistogram, U64Gauge};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_gauge_and_histogram() {
+        let registry = metric::Registry::new();
+
+        let gauge: metric::Metric<U64Gauge> =
+            registry.register_metric("disk_free_disk_space", "The percentage amount of disk available.");
+        gauge.recorder(&[("path", "/")]).set(42);
+
+        let histogram: metric::Metric<DurationHistogram> = registry
+            .register_metric("authz_permissions_duration", "duration of authz permissions check");
+        histogram
+            .recorder(&[("result", "success")])
+            .record(std::time::Duration::from_millis(5));
+
+        let rendered = PrometheusTextEncoder::encode(&registry);
+
+        assert!(rendered.contains("# HELP disk_free_disk_space"));
+        assert!(rendered.contains("# TYPE disk_free_disk_space gauge"));
+        assert!(rendered.contains(r#"disk_free_disk_space{path="/"} 42"#));
+
+        assert!(rendered.contains("# TYPE authz_permissions_duration histogram"));
+        assert!(rendered.contains("authz_permissions_duration_bucket{"));
+        assert!(rendered.contains(r#"result="success""#));
+        assert!(rendered.contains("authz_permissions_duration_sum{"));
+        assert!(rendered.contains("authz_permissions_duration_count{"));
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape_label_value("plain"), "plain");
+        assert_eq!(escape_label_value(r#"has "quotes""#), r#"has \"quotes\""#);
+        assert_eq!(escape_label_value(r"has\backslash"), r"has\\backslash");
+        assert_eq!(escape_label_value("has\nnewline"), "has\\nnewline");
+    }
+
+    #[test]
+    fn test_encode_escapes_label_values_with_newlines() {
+        let registry = metric::Registry::new();
+
+        let gauge: metric::Metric<U64Gauge> =
+            registry.register_metric("disk_free_disk_space", "The percentage amount of disk available.");
+        gauge.recorder(&[("path", "/weird\npath")]).set(1);
+
+        let rendered = PrometheusTextEncoder::encode(&registry);
+
+        // A literal newline in a label value must be escaped, not emitted
+        // as-is, or it would split this sample across two lines.
+        assert!(rendered.contains(r#"path="/weird\npath""#));
+        assert_eq!(
+            rendered.lines().filter(|l| l.starts_with("disk_free_disk_space{")).count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use std::{future::Future, time::Duration};
+
+use tokio::task::JoinHandle;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+/// Shared cooperative-shutdown plumbing for this crate's background
+/// instruments (e.g.
+/// [`InstrumentedDiskProtection`](crate::disk_protection::InstrumentedDiskProtection),
+/// [`InstrumentedMemoryProtection`](crate::memory_protection::InstrumentedMemoryProtection)).
+///
+/// Rather than [`JoinHandle::abort()`]ing a background task - which can
+/// interrupt a refresh mid-flight and gives no confirmation the loop
+/// actually stopped - the task is handed a [`CancellationToken`] to
+/// `tokio::select!` on alongside its own timer ticks, and is registered with
+/// a caller-supplied [`TaskTracker`] so a server can await every background
+/// instrument draining during graceful shutdown.
+#[derive(Debug)]
+pub(crate) struct CancellableTask {
+    token: CancellationToken,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl CancellableTask {
+    /// Spawn the future returned by `f` onto `tracker`, passing it a
+    /// [`CancellationToken`] it should select on to exit cleanly.
+    pub(crate) fn spawn<F, Fut>(tracker: &TaskTracker, f: F) -> Self
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let token = CancellationToken::new();
+        let handle = tracker.spawn(f(token.clone()));
+
+        Self {
+            token,
+            handle: Some(handle),
+        }
+    }
+
+    /// Cancel the task without waiting for it to exit.
+    ///
+    /// This is a best-effort, synchronous alternative to [`Self::shutdown()`]
+    /// for use from non-async contexts such as [`Drop`] impls - the task is
+    /// signalled to stop, but this does not confirm it has.
+    pub(crate) fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Cancel the task and await its join handle, falling back to
+    /// [`JoinHandle::abort()`] if it has not exited within `timeout`.
+    pub(crate) async fn shutdown(mut self, timeout: Duration) {
+        self.token.cancel();
+
+        let Some(handle) = self.handle.take() else {
+            return;
+        };
+
+        let abort_handle = handle.abort_handle();
+        if tokio::time::timeout(timeout, handle).await.is_err() {
+            abort_handle.abort();
+        }
+    }
+}
@@ -1,14 +1,29 @@
-use std::{borrow::Cow, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-use metric::{Attributes, U64Gauge};
+use metric::{Attributes, U64Counter, U64Gauge};
 use parking_lot::Mutex;
 use sysinfo::{DiskExt, System, SystemExt};
-use tokio::{self, task::JoinHandle};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+use crate::background_task::CancellableTask;
 
 /// Metrics that can be used to create a [`InstrumentedDiskProtection`].
 #[derive(Debug)]
 struct DiskProtectionMetrics {
     available_disk_space_percent: U64Gauge,
+    /// Whether writes are currently blocked by the protection circuit
+    /// breaker (1 = blocked, 0 = allowed).
+    protection_active: U64Gauge,
+    /// The number of times the circuit breaker has changed state.
+    protection_transitions: U64Counter,
     directory: PathBuf,
 }
 
@@ -23,16 +38,35 @@ impl DiskProtectionMetrics {
                 "disk_free_disk_space",
                 "The percentage amount of disk available.",
             )
+            .recorder(attributes.clone());
+
+        let protection_active = registry
+            .register_metric::<U64Gauge>(
+                "disk_protection_active",
+                "Whether writes are currently being blocked by the disk protection \
+                 circuit breaker (1 = blocked, 0 = allowed).",
+            )
+            .recorder(attributes.clone());
+
+        let protection_transitions = registry
+            .register_metric::<U64Counter>(
+                "disk_protection_transitions_total",
+                "The number of times the disk protection circuit breaker has changed state.",
+            )
             .recorder(attributes);
 
         Self {
             available_disk_space_percent,
+            protection_active,
+            protection_transitions,
             directory,
         }
     }
 
-    /// Measure the available disk space percentage.
-    pub(crate) fn measure_available_disk_space_percent(&self, system: &mut System) {
+    /// Measure the available disk space, returning the percentage and
+    /// absolute number of bytes free, or [`None`] if the tracked directory's
+    /// disk could not be found.
+    pub(crate) fn measure_available_disk_space(&self, system: &mut System) -> Option<(u64, u64)> {
         system.refresh_disks_list();
 
         let mut path = self.directory.clone();
@@ -49,25 +83,45 @@ impl DiskProtectionMetrics {
             }
         };
 
-        if let Some(disk) = fnd_disk {
-            disk.refresh();
+        let disk = fnd_disk?;
+        disk.refresh();
 
-            let available_disk: u64 = disk.available_space();
-            let total_disk: u64 = disk.total_space();
-            let available_disk_percentage =
-                ((available_disk as f64) / (total_disk as f64) * 100.0).round() as u64;
-            self.available_disk_space_percent
-                .set(available_disk_percentage);
-        }
+        let available_disk: u64 = disk.available_space();
+        let total_disk: u64 = disk.total_space();
+        let available_disk_percentage =
+            ((available_disk as f64) / (total_disk as f64) * 100.0).round() as u64;
+
+        self.available_disk_space_percent
+            .set(available_disk_percentage);
+
+        Some((available_disk_percentage, available_disk))
     }
 }
 
 /// Disk Protection instrument.
+///
+/// In addition to recording the available disk space as a metric, this
+/// instrument acts as a circuit breaker: once the tracked directory's free
+/// space drops below `min_free_percent` (or `min_free_bytes`, if set),
+/// [`Self::is_write_allowed()`] returns `false` until free space recovers
+/// above `min_free_percent` plus a hysteresis margin, preventing the
+/// breaker from flapping around the threshold.
 pub struct InstrumentedDiskProtection {
     /// The metrics that are reported to the registry.
     metrics: DiskProtectionMetrics,
-    /// The handle to terminate the background task.
-    background_task: Mutex<Option<JoinHandle<()>>>,
+    /// The minimum free disk space, as a percentage, before writes are
+    /// blocked.
+    min_free_percent: u64,
+    /// An optional minimum number of free bytes, below which writes are
+    /// blocked even if `min_free_percent` is satisfied.
+    min_free_bytes: Option<u64>,
+    /// The number of percentage points free space must recover above
+    /// `min_free_percent` before writes are unblocked again.
+    hysteresis_percent: u64,
+    /// A cheap, lock-free flag polled by the write path.
+    write_allowed: AtomicBool,
+    /// The handle to cooperatively terminate the background task.
+    background_task: Mutex<Option<CancellableTask>>,
 }
 
 impl std::fmt::Debug for InstrumentedDiskProtection {
@@ -78,50 +132,124 @@ impl std::fmt::Debug for InstrumentedDiskProtection {
 
 impl InstrumentedDiskProtection {
     /// Create a new [`InstrumentedDiskProtection`].
-    pub fn new(directory_to_track: PathBuf, registry: &metric::Registry) -> Self {
+    ///
+    /// Writes are blocked once free space on the disk backing
+    /// `directory_to_track` drops below `min_free_percent`, or below
+    /// `min_free_bytes` if provided. Once blocked, free space must recover
+    /// above `min_free_percent + hysteresis_percent` before writes are
+    /// allowed again.
+    pub fn new(
+        directory_to_track: PathBuf,
+        registry: &metric::Registry,
+        min_free_percent: u64,
+        min_free_bytes: Option<u64>,
+        hysteresis_percent: u64,
+    ) -> Self {
         let metrics = DiskProtectionMetrics::new(directory_to_track, registry);
 
         Self {
             metrics,
+            min_free_percent,
+            min_free_bytes,
+            hysteresis_percent,
+            write_allowed: AtomicBool::new(true),
             background_task: Default::default(),
         }
     }
 
-    /// Start the [`InstrumentedDiskProtection`] background task.
-    pub async fn start(self) {
-        let rc_self = Arc::new(self);
-        let rc_self_clone = Arc::clone(&rc_self);
+    /// Returns `true` if writes should currently be accepted.
+    ///
+    /// This is a cheap, lock-free check intended to be polled on the write
+    /// path before accepting a write.
+    pub fn is_write_allowed(&self) -> bool {
+        self.write_allowed.load(Ordering::Relaxed)
+    }
+
+    /// Start the [`InstrumentedDiskProtection`] background task, registering
+    /// it with `tracker` so a server can await it draining during graceful
+    /// shutdown. Returns the shared handle so callers can retain access to
+    /// [`Self::is_write_allowed()`] while the task runs.
+    pub async fn start(self: Arc<Self>, tracker: &TaskTracker) -> Arc<Self> {
+        let rc_self_clone = Arc::clone(&self);
 
-        *rc_self.background_task.lock() = Some(tokio::task::spawn(async move {
-            rc_self_clone.background_task().await
+        *self.background_task.lock() = Some(CancellableTask::spawn(tracker, move |token| async move {
+            rc_self_clone.background_task(token).await
         }));
+
+        self
     }
 
-    /// Stop the [`InstrumentedDiskProtection`] background task.
-    pub fn stop(&mut self) {
-        if let Some(t) = self.background_task.lock().take() {
-            t.abort()
+    /// Cancel the background task and await its exit, falling back to an
+    /// abort if it has not exited within `timeout`.
+    pub async fn shutdown(&self, timeout: Duration) {
+        let task = self.background_task.lock().take();
+        if let Some(task) = task {
+            task.shutdown(timeout).await;
         }
     }
 
-    /// The background task that periodically performs the disk protection check.
-    async fn background_task(&self) {
+    /// The background task that periodically performs the disk protection
+    /// check, until `token` is cancelled.
+    async fn background_task(&self, token: CancellationToken) {
         let mut system = System::new_all();
         let mut interval = tokio::time::interval(Duration::from_secs(10));
 
         loop {
-            self.metrics
-                .measure_available_disk_space_percent(&mut system);
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Some((available_percent, available_bytes)) =
+                        self.metrics.measure_available_disk_space(&mut system)
+                    {
+                        self.evaluate_protection(available_percent, available_bytes);
+                    }
+                }
+                _ = token.cancelled() => break,
+            }
+        }
+    }
+
+    /// Update [`Self::write_allowed`] based on the latest disk space
+    /// reading, applying [`Self::hysteresis_percent`] to the recovery
+    /// threshold to avoid flapping.
+    fn evaluate_protection(&self, available_percent: u64, available_bytes: u64) {
+        let below_threshold = available_percent < self.min_free_percent
+            || self
+                .min_free_bytes
+                .is_some_and(|min_free_bytes| available_bytes < min_free_bytes);
 
-            interval.tick().await;
+        let was_allowed = self.write_allowed.load(Ordering::Relaxed);
+
+        if was_allowed && below_threshold {
+            self.write_allowed.store(false, Ordering::Relaxed);
+            self.metrics.protection_active.set(1);
+            self.metrics.protection_transitions.inc(1);
+            return;
+        }
+
+        if !was_allowed {
+            let recovered = available_percent > self.min_free_percent + self.hysteresis_percent
+                && self
+                    .min_free_bytes
+                    .map_or(true, |min_free_bytes| available_bytes >= min_free_bytes);
+
+            if recovered {
+                self.write_allowed.store(true, Ordering::Relaxed);
+                self.metrics.protection_active.set(0);
+                self.metrics.protection_transitions.inc(1);
+            }
         }
     }
 }
 
 impl Drop for InstrumentedDiskProtection {
     fn drop(&mut self) {
-        // future-proof, such that stop does not need to be explicitly called.
-        self.stop();
+        // Best-effort, synchronous cancellation of any task still held here.
+        // A running background task holds its own `Arc` to this instrument,
+        // so this only runs once that task has already exited; it does not
+        // stop a running task - `shutdown()` does that.
+        if let Some(task) = self.background_task.lock().take() {
+            task.cancel();
+        }
     }
 }
 
@@ -135,18 +263,27 @@ mod tests {
     async fn test_metrics() {
         let registry = Arc::new(metric::Registry::new());
 
-        struct MockAnyStruct;
+        struct MockAnyStruct {
+            disk_protection: Arc<InstrumentedDiskProtection>,
+        }
 
         impl MockAnyStruct {
-            pub(crate) async fn new(registry: &metric::Registry) -> Self {
-                let disk_protection = InstrumentedDiskProtection::new(PathBuf::from("/"), registry);
-                disk_protection.start().await;
+            pub(crate) async fn new(registry: &metric::Registry, tracker: &TaskTracker) -> Self {
+                let disk_protection = Arc::new(InstrumentedDiskProtection::new(
+                    PathBuf::from("/"),
+                    registry,
+                    1,
+                    None,
+                    5,
+                ));
+                let disk_protection = disk_protection.start(tracker).await;
 
-                Self
+                Self { disk_protection }
             }
         }
 
-        let _mock = MockAnyStruct::new(&registry).await;
+        let tracker = TaskTracker::new();
+        let mock = MockAnyStruct::new(&registry, &tracker).await;
 
         tokio::time::sleep(2 * Duration::from_secs(2)).await;
 
@@ -158,5 +295,52 @@ mod tests {
             .fetch();
 
         assert!(recorded_metric > 0_u64);
+
+        // The root filesystem should have well above 1% free in any sane
+        // test environment, so writes should still be allowed.
+        assert!(mock.disk_protection.is_write_allowed());
+
+        mock.disk_protection
+            .shutdown(Duration::from_secs(1))
+            .await;
+        tracker.close();
+        tracker.wait().await;
+    }
+
+    #[test]
+    fn test_evaluate_protection_hysteresis() {
+        let registry = metric::Registry::new();
+        let disk_protection =
+            InstrumentedDiskProtection::new(PathBuf::from("/"), &registry, 10, None, 5);
+
+        assert!(disk_protection.is_write_allowed());
+
+        // Dropping below the threshold blocks writes.
+        disk_protection.evaluate_protection(5, 0);
+        assert!(!disk_protection.is_write_allowed());
+
+        // Recovering just above the threshold, but within the hysteresis
+        // margin, should NOT unblock writes yet.
+        disk_protection.evaluate_protection(12, 0);
+        assert!(!disk_protection.is_write_allowed());
+
+        // Recovering above the threshold plus the hysteresis margin
+        // unblocks writes.
+        disk_protection.evaluate_protection(16, 0);
+        assert!(disk_protection.is_write_allowed());
+    }
+
+    #[test]
+    fn test_evaluate_protection_min_free_bytes() {
+        let registry = metric::Registry::new();
+        let disk_protection =
+            InstrumentedDiskProtection::new(PathBuf::from("/"), &registry, 0, Some(1024), 0);
+
+        // Percentage is fine, but the absolute free bytes are too low.
+        disk_protection.evaluate_protection(50, 512);
+        assert!(!disk_protection.is_write_allowed());
+
+        disk_protection.evaluate_protection(50, 2048);
+        assert!(disk_protection.is_write_allowed());
     }
 }
@@ -0,0 +1,342 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use metric::{Attributes, U64Gauge, U64Histogram, U64HistogramOptions};
+use parking_lot::Mutex;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+use crate::background_task::CancellableTask;
+
+/// The smallest bucket boundary (1 MiB) for the peak-RSS distribution.
+const RSS_DISTRIBUTION_MIN_BYTES: u64 = 1024 * 1024;
+
+/// The largest bucket boundary (64 GiB) for the peak-RSS distribution.
+const RSS_DISTRIBUTION_MAX_BYTES: u64 = 64 * 1024 * 1024 * 1024;
+
+/// Build the exponentially-spaced (powers of two) bucket boundaries used for
+/// the peak RSS distribution, from [`RSS_DISTRIBUTION_MIN_BYTES`] to
+/// [`RSS_DISTRIBUTION_MAX_BYTES`] inclusive.
+fn rss_distribution_buckets() -> Vec<u64> {
+    let mut buckets = Vec::new();
+    let mut bound = RSS_DISTRIBUTION_MIN_BYTES;
+    while bound <= RSS_DISTRIBUTION_MAX_BYTES {
+        buckets.push(bound);
+        bound *= 2;
+    }
+    buckets
+}
+
+/// Metrics that can be used to create a [`InstrumentedMemoryProtection`].
+#[derive(Debug)]
+struct MemoryProtectionMetrics {
+    /// The peak RSS as reported by `getrusage(2)` at the last export tick.
+    max_rss_bytes: U64Gauge,
+    /// Distribution of the running-maximum RSS, sampled on every poll tick.
+    max_rss_distribution: U64Histogram,
+}
+
+impl MemoryProtectionMetrics {
+    /// Create a new [`MemoryProtectionMetrics`].
+    pub(crate) fn new(registry: &metric::Registry) -> Self {
+        let max_rss_bytes = registry
+            .register_metric::<U64Gauge>(
+                "process_memory_max_rss_bytes",
+                "The peak resident set size of this process, in bytes, as reported by getrusage().",
+            )
+            .recorder(Attributes::from([]));
+
+        let max_rss_distribution = registry
+            .register_metric_with_options::<U64Histogram, _>(
+                "process_memory_rss_distribution",
+                "Distribution of the running-maximum resident set size of this process, in bytes.",
+                || U64HistogramOptions::new(rss_distribution_buckets()),
+            )
+            .recorder(Attributes::from([]));
+
+        Self {
+            max_rss_bytes,
+            max_rss_distribution,
+        }
+    }
+
+    /// Sample peak RSS via `getrusage(2)` and record it.
+    pub(crate) fn measure_max_rss(&self) {
+        if let Some(bytes) = read_max_rss_bytes() {
+            self.max_rss_bytes.set(bytes);
+        }
+    }
+
+    /// Sample the current RSS, update `running_max` if it grew, and record
+    /// the running maximum into the distribution.
+    pub(crate) fn poll_current_rss(&self, running_max: &AtomicU64) {
+        let Some(bytes) = read_current_rss_bytes() else {
+            return;
+        };
+
+        let max = running_max.fetch_max(bytes, Ordering::Relaxed).max(bytes);
+        self.max_rss_distribution.record(max);
+    }
+}
+
+/// Read the peak (high-water mark) resident set size of this process, in
+/// bytes, normalising the platform-specific units reported by
+/// `getrusage(2)`.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn read_max_rss_bytes() -> Option<u64> {
+    // SAFETY: `rusage` is a plain-old-data struct populated entirely by the
+    // kernel; a zeroed value is a valid (if meaningless) starting state.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    let ret = unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+    if ret != 0 {
+        return None;
+    }
+
+    let maxrss = usage.ru_maxrss as u64;
+
+    // Linux reports ru_maxrss in kilobytes; macOS reports it in bytes.
+    #[cfg(target_os = "linux")]
+    let bytes = maxrss * 1024;
+    #[cfg(target_os = "macos")]
+    let bytes = maxrss;
+
+    Some(bytes)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_max_rss_bytes() -> Option<u64> {
+    None
+}
+
+/// Read the current (instantaneous) resident set size of this process, in
+/// bytes.
+#[cfg(target_os = "linux")]
+fn read_current_rss_bytes() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let resident_pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+
+    // SAFETY: `_SC_PAGESIZE` is always a supported sysconf() parameter.
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+
+    Some(resident_pages * page_size)
+}
+
+#[cfg(target_os = "macos")]
+fn read_current_rss_bytes() -> Option<u64> {
+    use std::mem;
+
+    let mut info: libc::mach_task_basic_info = unsafe { mem::zeroed() };
+    let mut count = (mem::size_of::<libc::mach_task_basic_info>() / mem::size_of::<libc::integer_t>())
+        as libc::mach_msg_type_number_t;
+
+    // SAFETY: `info` and `count` describe a buffer of the size `task_info()`
+    // expects for the `MACH_TASK_BASIC_INFO` flavor.
+    let ret = unsafe {
+        libc::task_info(
+            libc::mach_task_self(),
+            libc::MACH_TASK_BASIC_INFO,
+            &mut info as *mut _ as libc::task_info_t,
+            &mut count,
+        )
+    };
+
+    if ret != libc::KERN_SUCCESS {
+        return None;
+    }
+
+    Some(info.resident_size)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_current_rss_bytes() -> Option<u64> {
+    None
+}
+
+/// Memory Protection instrument.
+///
+/// Periodically samples this process's memory footprint and exports it as
+/// metrics: a `process_memory_max_rss_bytes` gauge reflecting the
+/// `getrusage()` high-water mark, and a `process_memory_rss_distribution`
+/// histogram tracking the running maximum of more frequently sampled,
+/// current RSS readings.
+pub struct InstrumentedMemoryProtection {
+    /// The metrics that are reported to the registry.
+    metrics: MemoryProtectionMetrics,
+    /// How often the current RSS is sampled to update the running maximum.
+    poll_interval: Duration,
+    /// How often the `getrusage()` high-water mark gauge is refreshed.
+    export_interval: Duration,
+    /// The running maximum of the current RSS samples, in bytes.
+    running_max_rss: AtomicU64,
+    /// The handle to cooperatively terminate the background task.
+    background_task: Mutex<Option<CancellableTask>>,
+}
+
+impl std::fmt::Debug for InstrumentedMemoryProtection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InstrumentedMemoryProtection")
+    }
+}
+
+impl InstrumentedMemoryProtection {
+    /// Create a new [`InstrumentedMemoryProtection`], polling the current RSS
+    /// every `poll_interval` and refreshing the `getrusage()` gauge every
+    /// `export_interval`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `poll_interval` or `export_interval` is zero -
+    /// `tokio::time::interval()` itself panics on a zero duration, so this
+    /// is validated here, at construction time, rather than failing deep
+    /// inside the background task once it is started.
+    pub fn new(registry: &metric::Registry, poll_interval: Duration, export_interval: Duration) -> Self {
+        assert!(
+            !poll_interval.is_zero(),
+            "InstrumentedMemoryProtection poll_interval must be non-zero"
+        );
+        assert!(
+            !export_interval.is_zero(),
+            "InstrumentedMemoryProtection export_interval must be non-zero"
+        );
+
+        let metrics = MemoryProtectionMetrics::new(registry);
+
+        Self {
+            metrics,
+            poll_interval,
+            export_interval,
+            running_max_rss: AtomicU64::new(0),
+            background_task: Default::default(),
+        }
+    }
+
+    /// Start the [`InstrumentedMemoryProtection`] background task, registering
+    /// it with `tracker` so a server can await it draining during graceful
+    /// shutdown. Returns the shared handle so callers can retain access to
+    /// this instrument while the task runs.
+    pub async fn start(self: Arc<Self>, tracker: &TaskTracker) -> Arc<Self> {
+        let rc_self_clone = Arc::clone(&self);
+
+        *self.background_task.lock() = Some(CancellableTask::spawn(tracker, move |token| async move {
+            rc_self_clone.background_task(token).await
+        }));
+
+        self
+    }
+
+    /// Cancel the background task and await its exit, falling back to an
+    /// abort if it has not exited within `timeout`.
+    pub async fn shutdown(&self, timeout: Duration) {
+        let task = self.background_task.lock().take();
+        if let Some(task) = task {
+            task.shutdown(timeout).await;
+        }
+    }
+
+    /// The background task that periodically polls and exports memory
+    /// usage, until `token` is cancelled.
+    async fn background_task(&self, token: CancellationToken) {
+        let mut poll_interval = tokio::time::interval(self.poll_interval);
+        let mut export_interval = tokio::time::interval(self.export_interval);
+
+        loop {
+            tokio::select! {
+                _ = poll_interval.tick() => {
+                    self.metrics.poll_current_rss(&self.running_max_rss);
+                }
+                _ = export_interval.tick() => {
+                    self.metrics.measure_max_rss();
+                }
+                _ = token.cancelled() => break,
+            }
+        }
+    }
+}
+
+impl Drop for InstrumentedMemoryProtection {
+    fn drop(&mut self) {
+        // Best-effort, synchronous cancellation of any task still held here.
+        // A running background task holds its own `Arc` to this instrument,
+        // so this only runs once that task has already exited; it does not
+        // stop a running task - `shutdown()` does that.
+        if let Some(task) = self.background_task.lock().take() {
+            task.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metric::Metric;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let registry = Arc::new(metric::Registry::new());
+
+        struct MockAnyStruct {
+            memory_protection: Arc<InstrumentedMemoryProtection>,
+        }
+
+        impl MockAnyStruct {
+            pub(crate) async fn new(registry: &metric::Registry, tracker: &TaskTracker) -> Self {
+                let memory_protection = Arc::new(InstrumentedMemoryProtection::new(
+                    registry,
+                    Duration::from_millis(100),
+                    Duration::from_secs(10),
+                ));
+                let memory_protection = memory_protection.start(tracker).await;
+
+                Self { memory_protection }
+            }
+        }
+
+        let tracker = TaskTracker::new();
+        let mock = MockAnyStruct::new(&registry, &tracker).await;
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let recorded_metric = registry
+            .get_instrument::<Metric<U64Gauge>>("process_memory_max_rss_bytes")
+            .expect("metric should exist")
+            .get_observer(&Attributes::from([]))
+            .expect("metric should have labels")
+            .fetch();
+
+        assert!(recorded_metric > 0_u64);
+
+        let recorded_histogram = registry
+            .get_instrument::<Metric<U64Histogram>>("process_memory_rss_distribution")
+            .expect("metric should exist")
+            .get_observer(&Attributes::from([]))
+            .expect("metric should have labels")
+            .fetch();
+
+        assert!(recorded_histogram.sample_count() > 0);
+
+        mock.memory_protection
+            .shutdown(Duration::from_secs(1))
+            .await;
+        tracker.close();
+        tracker.wait().await;
+    }
+
+    #[test]
+    #[should_panic(expected = "poll_interval must be non-zero")]
+    fn test_new_rejects_zero_poll_interval() {
+        let registry = metric::Registry::new();
+        InstrumentedMemoryProtection::new(&registry, Duration::ZERO, Duration::from_secs(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "export_interval must be non-zero")]
+    fn test_new_rejects_zero_export_interval() {
+        let registry = metric::Registry::new();
+        InstrumentedMemoryProtection::new(&registry, Duration::from_millis(100), Duration::ZERO);
+    }
+}